@@ -0,0 +1,254 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::process::Command;
+use std::time::Duration;
+
+use hickory_resolver::config::{NameServerConfig, Protocol, ResolverConfig, ResolverOpts};
+use hickory_resolver::TokioAsyncResolver;
+
+mod network_manager;
+mod resolvectl;
+
+pub use network_manager::NetworkManagerBackend;
+pub use resolvectl::ResolvectlBackend;
+
+/// Domains resolved against newly-applied DNS servers to confirm they
+/// actually work before committing to the change.
+const HEALTH_CHECK_DOMAINS: &[&str] = &["cloudflare.com", "example.com"];
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Abstracts the system mechanism used to read and change DNS settings, so
+/// the rest of the app doesn't need to know whether it's talking to
+/// NetworkManager, systemd-resolved, or a plain `/etc/resolv.conf`.
+#[async_trait]
+pub trait DnsBackend: Send + Sync {
+    /// Returns an identifier (NetworkManager connection name, or network
+    /// interface name) that the other methods operate on.
+    async fn get_active_connection(&self) -> Result<String>;
+
+    /// Applies `primary`/`secondary` as the DNS servers for `connection`,
+    /// optionally over DNS-over-TLS when `dot_hostname` is set. Verifies
+    /// the change actually resolves real domains and rolls back to the
+    /// previous settings if it doesn't.
+    async fn set_dns(
+        &self,
+        connection: &str,
+        primary: &str,
+        secondary: &str,
+        dot_hostname: Option<&str>,
+    ) -> Result<()>;
+
+    /// Reverts `connection` to automatic (router/DHCP-provided) DNS.
+    async fn set_automatic(&self, connection: &str) -> Result<()>;
+
+    /// Returns the DNS servers actually in effect for `connection` right
+    /// now (e.g. the router/DHCP-provided ones), resolved down to a real
+    /// link rather than assuming `connection` itself is one.
+    async fn current_servers(&self, connection: &str) -> Result<Vec<String>>;
+
+    /// Prints this backend's view of the current DNS configuration for
+    /// `connection`, plus the shared resolv.conf/resolvectl reconciliation.
+    async fn show_current(&self, connection: &str) -> Result<()>;
+}
+
+/// Detects which backend is usable on this system, preferring
+/// NetworkManager (the common desktop default) and falling back to
+/// `resolvectl`/systemd-resolved.
+pub fn detect() -> Result<Box<dyn DnsBackend>> {
+    if command_exists("nmcli") {
+        return Ok(Box::new(NetworkManagerBackend));
+    }
+    if command_exists("resolvectl") {
+        return Ok(Box::new(ResolvectlBackend));
+    }
+    Err(anyhow!("No supported DNS backend found (need NetworkManager or systemd-resolved)"))
+}
+
+fn command_exists(name: &str) -> bool {
+    Command::new("which")
+        .arg(name)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Resolves `HEALTH_CHECK_DOMAINS` directly against `server`, bypassing any
+/// OS-level cache, and reports whether all of them succeeded.
+pub(crate) async fn verify_resolution(server: &str) -> bool {
+    let ip: IpAddr = match server.parse() {
+        Ok(ip) => ip,
+        Err(_) => return false,
+    };
+
+    let mut config = ResolverConfig::new();
+    config.add_name_server(NameServerConfig {
+        socket_addr: SocketAddr::new(ip, 53),
+        protocol: Protocol::Udp,
+        tls_dns_name: None,
+        trust_negative_responses: false,
+        bind_addr: None,
+    });
+
+    let mut opts = ResolverOpts::default();
+    opts.timeout = HEALTH_CHECK_TIMEOUT;
+    opts.attempts = 1;
+    opts.cache_size = 0;
+
+    let resolver = TokioAsyncResolver::tokio(config, opts);
+
+    for domain in HEALTH_CHECK_DOMAINS {
+        if resolver.lookup_ip(*domain).await.is_err() {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Parsed contents of `/etc/resolv.conf`.
+struct ResolvConf {
+    nameservers: Vec<String>,
+    search: Vec<String>,
+    options: Vec<String>,
+    /// True when the file is a symlink into systemd-resolved's stub
+    /// resolver (`/run/systemd/resolve/stub-resolv.conf`), in which case
+    /// its `nameserver` line is always `127.0.0.53` regardless of the
+    /// actual upstream configured via NetworkManager/resolvectl.
+    is_stub_symlink: bool,
+}
+
+/// DNS servers `resolvectl status` reports for a single network link.
+struct ResolvectlLink {
+    name: String,
+    dns_servers: Vec<String>,
+}
+
+/// Parses `/etc/resolv.conf`, handling `nameserver`, `search`, and
+/// `options` lines and ignoring comments, and notes whether the file is a
+/// symlink into systemd-resolved's stub resolver.
+fn parse_resolv_conf() -> Result<ResolvConf> {
+    let path = "/etc/resolv.conf";
+    let is_stub_symlink = fs::read_link(path)
+        .map(|target| target.to_string_lossy().contains("stub-resolv"))
+        .unwrap_or(false);
+
+    let contents = fs::read_to_string(path)?;
+    let mut nameservers = Vec::new();
+    let mut search = Vec::new();
+    let mut options = Vec::new();
+
+    for raw_line in contents.lines() {
+        let line = raw_line.split(['#', ';']).next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("nameserver") => nameservers.extend(fields.map(|s| s.to_string())),
+            Some("search") => search.extend(fields.map(|s| s.to_string())),
+            Some("options") => options.extend(fields.map(|s| s.to_string())),
+            _ => {}
+        }
+    }
+
+    Ok(ResolvConf { nameservers, search, options, is_stub_symlink })
+}
+
+/// Parses `resolvectl status` into per-link DNS server lists.
+fn parse_resolvectl_links() -> Vec<ResolvectlLink> {
+    let output = match Command::new("resolvectl").arg("status").output() {
+        Ok(o) if o.status.success() => o,
+        _ => return Vec::new(),
+    };
+    let text = String::from_utf8_lossy(&output.stdout);
+
+    let mut links = Vec::new();
+    let mut current_name: Option<String> = None;
+    let mut current_servers = Vec::new();
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix("Link ") {
+            if let Some(name) = current_name.take() {
+                links.push(ResolvectlLink { name, dns_servers: std::mem::take(&mut current_servers) });
+            }
+            current_name = rest.split('(').nth(1).map(|s| s.trim_end_matches(')').to_string());
+        } else if let Some(rest) = trimmed.strip_prefix("DNS Servers:") {
+            if current_name.is_some() {
+                current_servers.extend(rest.split_whitespace().map(|s| s.to_string()));
+            }
+        }
+    }
+    if let Some(name) = current_name {
+        links.push(ResolvectlLink { name, dns_servers: current_servers });
+    }
+
+    links
+}
+
+/// The systemd-resolved stub resolver address. A resolv.conf whose only
+/// nameserver is this is the normal, working state on any system that
+/// relies on the stub rather than writing real servers into resolv.conf.
+const STUB_RESOLVER_ADDR: &str = "127.0.0.53";
+
+/// Prints the shared resolv.conf/resolvectl reconciliation view and warns
+/// when `expected_dns` (what the active backend believes it set) doesn't
+/// match what's actually in effect — e.g. NetworkManager says one thing
+/// but resolv.conf is a static file that won't pick up the change.
+///
+/// On a stub-resolver system, `/etc/resolv.conf` legitimately only ever
+/// contains `127.0.0.53`, so the comparison is made against the per-link
+/// servers `resolvectl` reports instead, which reflect what's actually
+/// queried.
+pub(crate) fn print_resolver_reconciliation(expected_dns: &[String]) -> Result<()> {
+    let resolv_conf = parse_resolv_conf()?;
+    let links = parse_resolvectl_links();
+    let is_stub_resolver = resolv_conf.is_stub_symlink
+        || resolv_conf.nameservers.iter().all(|ns| ns == STUB_RESOLVER_ADDR);
+
+    if resolv_conf.is_stub_symlink {
+        println!("  /etc/resolv.conf -> systemd-resolved stub resolver (127.0.0.53)");
+    }
+    println!("  /etc/resolv.conf nameservers: {}", resolv_conf.nameservers.join(", "));
+    if !resolv_conf.search.is_empty() {
+        println!("  /etc/resolv.conf search domains: {}", resolv_conf.search.join(", "));
+    }
+    if !resolv_conf.options.is_empty() {
+        println!("  /etc/resolv.conf options: {}", resolv_conf.options.join(", "));
+    }
+
+    for link in &links {
+        println!("  {}: {}", link.name, link.dns_servers.join(", "));
+    }
+
+    if expected_dns.is_empty() {
+        return Ok(());
+    }
+
+    let mismatch = if is_stub_resolver {
+        !links.iter().any(|link| link.dns_servers.iter().any(|s| expected_dns.contains(s)))
+    } else {
+        !resolv_conf.nameservers.iter().any(|ns| expected_dns.contains(ns))
+    };
+
+    if mismatch {
+        if is_stub_resolver {
+            println!(
+                "\n⚠️  The active backend reports {:?} but no link's resolvectl servers \
+                match — your change may not take effect",
+                expected_dns
+            );
+        } else {
+            println!(
+                "\n⚠️  The active backend reports {:?} but /etc/resolv.conf has {:?} — \
+                your change may not take effect (static file?)",
+                expected_dns, resolv_conf.nameservers
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,274 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::net::IpAddr;
+use std::process::Command;
+
+use super::{print_resolver_reconciliation, verify_resolution, DnsBackend};
+
+/// Splits `servers` into nmcli's `ipv4.dns`/`ipv6.dns` property values
+/// based on each address's family, tagging each with `#hostname` when
+/// `dot_hostname` is set. Empty entries are skipped; entries that don't
+/// parse as an IP address are put in the `ipv4.dns` value.
+fn split_by_family(servers: &[&str], dot_hostname: Option<&str>) -> (String, String) {
+    let mut v4 = Vec::new();
+    let mut v6 = Vec::new();
+
+    for server in servers {
+        if server.is_empty() {
+            continue;
+        }
+        let tagged = match dot_hostname {
+            Some(hostname) => format!("{}#{}", server, hostname),
+            None => server.to_string(),
+        };
+        match server.parse::<IpAddr>() {
+            Ok(IpAddr::V4(_)) => v4.push(tagged),
+            Ok(IpAddr::V6(_)) => v6.push(tagged),
+            Err(_) => v4.push(tagged),
+        }
+    }
+
+    (v4.join(" "), v6.join(" "))
+}
+
+/// Snapshot of the DNS-related connection properties taken before a
+/// change, so `set_dns` can roll back if the new servers don't work.
+struct PreviousDnsSettings {
+    dns: String,
+    ignore_auto_dns: String,
+    dns6: String,
+    ignore_auto_dns6: String,
+    dns_over_tls: String,
+}
+
+/// DNS backend for NetworkManager-managed desktops, driven entirely
+/// through `nmcli`.
+pub struct NetworkManagerBackend;
+
+#[async_trait]
+impl DnsBackend for NetworkManagerBackend {
+    async fn get_active_connection(&self) -> Result<String> {
+        let output = Command::new("nmcli")
+            .arg("-t")
+            .arg("-f")
+            .arg("NAME,DEVICE")
+            .arg("connection")
+            .arg("show")
+            .arg("--active")
+            .output()?;
+
+        if !output.status.success() {
+            return Err(anyhow!("Failed to get active connections"));
+        }
+
+        let output_str = String::from_utf8(output.stdout)?;
+
+        for line in output_str.lines() {
+            let parts: Vec<&str> = line.split(':').collect();
+            if parts.len() >= 2 && !parts[1].is_empty() {
+                return Ok(parts[0].to_string());
+            }
+        }
+
+        Err(anyhow!("No active connection found"))
+    }
+
+    /// Applies `primary`/`secondary` as the connection's DNS servers,
+    /// routing each to `ipv4.dns` or `ipv6.dns` depending on its address
+    /// family so IPv6 providers aren't silently handed to the v4 property
+    /// (which nmcli rejects). When `dot_hostname` is set, tags each server
+    /// with NetworkManager's `ip#hostname` TLS syntax and enables strict
+    /// DNS-over-TLS so queries are encrypted rather than sent in plaintext.
+    ///
+    /// After applying, verifies the new servers actually resolve a couple
+    /// of known domains. If they don't, the previous settings (captured
+    /// beforehand) are restored so a bad entry can't leave the machine
+    /// without working name resolution.
+    async fn set_dns(
+        &self,
+        connection: &str,
+        primary: &str,
+        secondary: &str,
+        dot_hostname: Option<&str>,
+    ) -> Result<()> {
+        let previous = self.capture_dns_settings(connection)?;
+
+        let (v4_dns, v6_dns) = split_by_family(&[primary, secondary], dot_hostname);
+
+        self.execute_command(&[
+            "connection", "mod", connection,
+            "ipv4.dns", &v4_dns,
+            "ipv4.ignore-auto-dns", if v4_dns.is_empty() { "no" } else { "yes" },
+            "ipv6.dns", &v6_dns,
+            "ipv6.ignore-auto-dns", if v6_dns.is_empty() { "no" } else { "yes" },
+            "connection.dns-over-tls", if dot_hostname.is_some() { "yes" } else { "no" },
+        ])?;
+        self.restart_connection(connection)?;
+
+        if verify_resolution(primary).await {
+            Ok(())
+        } else {
+            println!("⚠️  New DNS servers failed to resolve test domains, rolling back...");
+            self.restore_dns_settings(connection, &previous)?;
+            self.restart_connection(connection)?;
+            Err(anyhow!("DNS verification failed against {}; rolled back to previous settings", primary))
+        }
+    }
+
+    async fn set_automatic(&self, connection: &str) -> Result<()> {
+        self.execute_command(&[
+            "connection", "mod", connection,
+            "ipv4.dns", "",
+            "ipv4.ignore-auto-dns", "no",
+            "ipv6.dns", "",
+            "ipv6.ignore-auto-dns", "no",
+            "connection.dns-over-tls", "no",
+        ])?;
+
+        self.restart_connection(connection)?;
+        println!("✅ Switched to automatic DNS (Router)");
+        Ok(())
+    }
+
+    /// Looks up the device backing `connection` and asks `resolvectl` what
+    /// it's actually using, so the router/DHCP-provided servers can be
+    /// used as a benchmark baseline. `connection` is an NM connection
+    /// name, not a link, so it can't be passed to `resolvectl` directly.
+    async fn current_servers(&self, connection: &str) -> Result<Vec<String>> {
+        let device = self.get_connection_field(connection, "GENERAL.DEVICE")?;
+        if device.is_empty() {
+            return Err(anyhow!("Could not determine the device backing {}", connection));
+        }
+
+        let output = Command::new("resolvectl")
+            .arg("dns")
+            .arg(&device)
+            .output()?;
+
+        let servers: Vec<String> = String::from_utf8_lossy(&output.stdout)
+            .split(':')
+            .nth(1)
+            .unwrap_or("")
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect();
+
+        if servers.is_empty() {
+            return Err(anyhow!("resolvectl reported no DNS servers for {}", device));
+        }
+
+        Ok(servers)
+    }
+
+    async fn show_current(&self, connection: &str) -> Result<()> {
+        let output = Command::new("nmcli")
+            .arg("connection")
+            .arg("show")
+            .arg(connection)
+            .output()?;
+
+        if output.status.success() {
+            let output_str = String::from_utf8(output.stdout)?;
+            for line in output_str.lines() {
+                if line.contains("ipv4.dns") || line.contains("ipv4.ignore-auto-dns")
+                    || line.contains("connection.dns-over-tls") {
+                    println!("{}", line);
+                }
+            }
+        }
+
+        let dot_enabled = self.get_connection_field(connection, "connection.dns-over-tls")
+            .map(|v| v == "yes")
+            .unwrap_or(false);
+        println!("Encrypted DNS (DoT): {}", if dot_enabled { "enabled" } else { "disabled" });
+
+        let dns_field = self.get_connection_field(connection, "ipv4.dns").unwrap_or_default();
+        let expected: Vec<String> = dns_field
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect();
+
+        println!("\nSystem resolver state:");
+        print_resolver_reconciliation(&expected)?;
+
+        Ok(())
+    }
+}
+
+impl NetworkManagerBackend {
+    fn execute_command(&self, args: &[&str]) -> Result<()> {
+        let output = Command::new("sudo")
+            .arg("nmcli")
+            .args(args)
+            .output()?;
+
+        if !output.status.success() {
+            return Err(anyhow!("Command failed: {}", String::from_utf8_lossy(&output.stderr)));
+        }
+
+        Ok(())
+    }
+
+    fn restart_connection(&self, connection: &str) -> Result<()> {
+        // Bring connection down
+        let _ = Command::new("sudo")
+            .arg("nmcli")
+            .arg("connection")
+            .arg("down")
+            .arg(connection)
+            .output();
+
+        // Bring connection up
+        let output = Command::new("sudo")
+            .arg("nmcli")
+            .arg("connection")
+            .arg("up")
+            .arg(connection)
+            .output()?;
+
+        if !output.status.success() {
+            return Err(anyhow!("Failed to restart connection: {}",
+                String::from_utf8_lossy(&output.stderr)));
+        }
+
+        Ok(())
+    }
+
+    fn get_connection_field(&self, connection: &str, field: &str) -> Result<String> {
+        let output = Command::new("nmcli")
+            .arg("-g")
+            .arg(field)
+            .arg("connection")
+            .arg("show")
+            .arg(connection)
+            .output()?;
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Reads the connection's current `ipv4.dns`/`ipv6.dns`,
+    /// `ipv4.ignore-auto-dns`/`ipv6.ignore-auto-dns`, and
+    /// `connection.dns-over-tls` properties so `set_dns` can restore them
+    /// if the new servers turn out not to work.
+    fn capture_dns_settings(&self, connection: &str) -> Result<PreviousDnsSettings> {
+        Ok(PreviousDnsSettings {
+            dns: self.get_connection_field(connection, "ipv4.dns")?,
+            ignore_auto_dns: self.get_connection_field(connection, "ipv4.ignore-auto-dns")?,
+            dns6: self.get_connection_field(connection, "ipv6.dns")?,
+            ignore_auto_dns6: self.get_connection_field(connection, "ipv6.ignore-auto-dns")?,
+            dns_over_tls: self.get_connection_field(connection, "connection.dns-over-tls")?,
+        })
+    }
+
+    fn restore_dns_settings(&self, connection: &str, previous: &PreviousDnsSettings) -> Result<()> {
+        self.execute_command(&[
+            "connection", "mod", connection,
+            "ipv4.dns", &previous.dns,
+            "ipv4.ignore-auto-dns", &previous.ignore_auto_dns,
+            "ipv6.dns", &previous.dns6,
+            "ipv6.ignore-auto-dns", &previous.ignore_auto_dns6,
+            "connection.dns-over-tls", &previous.dns_over_tls,
+        ])
+    }
+}
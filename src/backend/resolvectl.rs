@@ -0,0 +1,127 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::process::Command;
+
+use super::{print_resolver_reconciliation, verify_resolution, DnsBackend};
+
+/// DNS backend for systems without NetworkManager, driven through
+/// `resolvectl` (systemd-resolved) against the interface carrying the
+/// default route.
+pub struct ResolvectlBackend;
+
+#[async_trait]
+impl DnsBackend for ResolvectlBackend {
+    async fn get_active_connection(&self) -> Result<String> {
+        let output = Command::new("ip")
+            .arg("route")
+            .arg("show")
+            .arg("default")
+            .output()?;
+
+        if !output.status.success() {
+            return Err(anyhow!("Failed to determine the default route"));
+        }
+
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        output_str
+            .split_whitespace()
+            .skip_while(|word| *word != "dev")
+            .nth(1)
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow!("Could not determine the default network interface"))
+    }
+
+    /// Sets the per-interface DNS servers and DoT policy via `resolvectl`,
+    /// then verifies the change resolves real domains, rolling back to
+    /// the previously configured servers if it doesn't.
+    async fn set_dns(
+        &self,
+        connection: &str,
+        primary: &str,
+        secondary: &str,
+        dot_hostname: Option<&str>,
+    ) -> Result<()> {
+        let previous = self.current_dns(connection).unwrap_or_default();
+
+        let mut servers = vec![primary.to_string()];
+        if !secondary.is_empty() {
+            servers.push(secondary.to_string());
+        }
+        self.run_dns(connection, &servers)?;
+        self.run(&["dns-over-tls", connection, if dot_hostname.is_some() { "yes" } else { "no" }])?;
+
+        if verify_resolution(primary).await {
+            Ok(())
+        } else {
+            println!("⚠️  New DNS servers failed to resolve test domains, rolling back...");
+            if !previous.is_empty() {
+                let _ = self.run_dns(connection, &previous);
+            }
+            Err(anyhow!("DNS verification failed against {}; rolled back to previous servers", primary))
+        }
+    }
+
+    async fn set_automatic(&self, connection: &str) -> Result<()> {
+        self.run(&["revert", connection])?;
+        println!("✅ Switched to automatic DNS (Router)");
+        Ok(())
+    }
+
+    /// Here `connection` is already the interface, so this is
+    /// `current_dns`, except that an empty server list is an error.
+    async fn current_servers(&self, connection: &str) -> Result<Vec<String>> {
+        let servers = self.current_dns(connection)?;
+        if servers.is_empty() {
+            return Err(anyhow!("resolvectl reported no DNS servers for {}", connection));
+        }
+        Ok(servers)
+    }
+
+    async fn show_current(&self, connection: &str) -> Result<()> {
+        let _ = Command::new("resolvectl").arg("status").arg(connection).status();
+
+        let expected = self.current_dns(connection).unwrap_or_default();
+        println!("\nSystem resolver state:");
+        print_resolver_reconciliation(&expected)?;
+
+        Ok(())
+    }
+}
+
+impl ResolvectlBackend {
+    fn run(&self, args: &[&str]) -> Result<()> {
+        let output = Command::new("sudo")
+            .arg("resolvectl")
+            .args(args)
+            .output()?;
+
+        if !output.status.success() {
+            return Err(anyhow!("Command failed: {}", String::from_utf8_lossy(&output.stderr)));
+        }
+
+        Ok(())
+    }
+
+    fn run_dns(&self, connection: &str, servers: &[String]) -> Result<()> {
+        let mut args = vec!["dns".to_string(), connection.to_string()];
+        args.extend(servers.iter().cloned());
+        let arg_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        self.run(&arg_refs)
+    }
+
+    fn current_dns(&self, connection: &str) -> Result<Vec<String>> {
+        let output = Command::new("resolvectl")
+            .arg("dns")
+            .arg(connection)
+            .output()?;
+
+        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(text
+            .split(':')
+            .nth(1)
+            .unwrap_or("")
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect())
+    }
+}
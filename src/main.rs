@@ -1,7 +1,52 @@
 use anyhow::{anyhow, Result};
-use dialoguer::{Select, Input};
-use std::process::Command;
+use dialoguer::{Confirm, Select, Input};
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
+use hickory_resolver::config::{NameServerConfig, Protocol, ResolverConfig, ResolverOpts};
+use hickory_resolver::TokioAsyncResolver;
+use hickory_client::client::AsyncClient;
+use hickory_client::op::{DnsResponse, Edns, Message, MessageType, OpCode, Query, ResponseCode};
+use hickory_client::rr::{DNSClass, Name, RecordType};
+use hickory_client::udp::UdpClientStream;
+use hickory_proto::xfer::{DnsRequest, DnsRequestOptions};
+use hickory_proto::DnsHandle;
+use futures_util::StreamExt;
+
+mod backend;
+use backend::DnsBackend;
+
+/// Domains queried during a benchmark run. Kept small and popular so a
+/// single ISP/CDN hiccup doesn't skew the result for one provider only.
+const BENCHMARK_DOMAINS: &[&str] = &["google.com", "cloudflare.com", "amazon.com"];
+
+/// Number of probes per server, cycling through `BENCHMARK_DOMAINS`. Low
+/// enough to keep a benchmark run fast, high enough that one dropped packet
+/// doesn't dominate the score.
+const PROBES_PER_SERVER: usize = 4;
+
+/// Per-query timeout. A non-response is scored as `TIMEOUT_PENALTY_MS`
+/// rather than excluded, so flaky servers still rank behind reliable ones.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+const TIMEOUT_PENALTY_MS: f64 = 2000.0;
+
+/// Smoothing factor for the running latency score: `score = EWMA_ALPHA *
+/// old + (1.0 - EWMA_ALPHA) * sample_ms`.
+const EWMA_ALPHA: f64 = 0.7;
+
+/// Path to the user's provider config, relative to the XDG config dir
+/// (typically `~/.config`).
+const CONFIG_RELATIVE_PATH: &str = "dns_changer/providers.toml";
+
+/// A known DNSSEC-signed domain, used to check that a provider returns
+/// RRSIG records and sets the AD flag when asked.
+const DNSSEC_SIGNED_DOMAIN: &str = "cloudflare.com";
+
+/// A domain with a deliberately broken signature. A validating resolver
+/// must answer SERVFAIL here rather than passing the bogus record through.
+const DNSSEC_BOGUS_DOMAIN: &str = "dnssec-failed.org";
 
 #[derive(Debug, Serialize, Deserialize)]
 struct DnsProvider {
@@ -9,77 +54,128 @@ struct DnsProvider {
     primary_dns: String,
     secondary_dns: String,
     description: String,
+    /// TLS server name for DNS-over-TLS (e.g. `cloudflare-dns.com`). When
+    /// set, `set_dns` wires up the `ip#hostname` syntax NetworkManager
+    /// expects and enables strict DoT for the connection.
+    #[serde(default)]
+    dot_hostname: Option<String>,
+}
+
+/// On-disk shape of `providers.toml`: a single `providers = [...]` table
+/// mirroring `DnsProvider` so users can add their own servers, including
+/// IPv6 addresses, without recompiling.
+#[derive(Debug, Serialize, Deserialize)]
+struct ProvidersConfig {
+    providers: Vec<DnsProvider>,
+}
+
+/// Result of a single DNSSEC probe query against one domain.
+struct DnssecQueryResult {
+    has_rrsig: bool,
+    ad_flag: bool,
+    rcode: ResponseCode,
+}
+
+#[derive(Debug)]
+struct BenchmarkResult {
+    name: String,
+    primary_dns: String,
+    secondary_dns: String,
+    score_ms: f64,
+    failures: usize,
+    probes: usize,
 }
 
 struct DnsChanger {
     providers: Vec<DnsProvider>,
     current_connection: String,
+    backend: Box<dyn DnsBackend>,
 }
 
 impl DnsChanger {
-    fn new() -> Result<Self> {
-        let providers = vec![
+    async fn new() -> Result<Self> {
+        let providers = Self::load_providers()?;
+        let backend = backend::detect()?;
+        let current_connection = backend.get_active_connection().await?;
+
+        Ok(Self {
+            providers,
+            current_connection,
+            backend,
+        })
+    }
+
+    fn default_providers() -> Vec<DnsProvider> {
+        vec![
             DnsProvider {
                 name: "Cloudflare".to_string(),
                 primary_dns: "1.1.1.1".to_string(),
                 secondary_dns: "1.0.0.1".to_string(),
                 description: "Fast and privacy-focused DNS".to_string(),
+                dot_hostname: Some("cloudflare-dns.com".to_string()),
             },
             DnsProvider {
                 name: "Google".to_string(),
                 primary_dns: "8.8.8.8".to_string(),
                 secondary_dns: "8.8.4.4".to_string(),
                 description: "Reliable Google DNS".to_string(),
+                dot_hostname: Some("dns.google".to_string()),
             },
             DnsProvider {
                 name: "Quad9".to_string(),
                 primary_dns: "9.9.9.9".to_string(),
                 secondary_dns: "149.112.112.112".to_string(),
                 description: "Security-focused DNS".to_string(),
+                dot_hostname: Some("dns.quad9.net".to_string()),
             },
             DnsProvider {
                 name: "OpenDNS".to_string(),
                 primary_dns: "208.67.222.222".to_string(),
                 secondary_dns: "208.67.220.220".to_string(),
                 description: "Family-safe DNS".to_string(),
+                dot_hostname: None,
             },
-        ];
-
-        let current_connection = DnsChanger::get_active_connection()?;
+        ]
+    }
 
-        Ok(Self {
-            providers,
-            current_connection,
-        })
+    fn config_file_path() -> Result<PathBuf> {
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| anyhow!("Could not determine user config directory"))?;
+        Ok(config_dir.join(CONFIG_RELATIVE_PATH))
     }
 
-    fn get_active_connection() -> Result<String> {
-        let output = Command::new("nmcli")
-            .arg("-t")
-            .arg("-f")
-            .arg("NAME,DEVICE")
-            .arg("connection")
-            .arg("show")
-            .arg("--active")
-            .output()?;
-
-        if !output.status.success() {
-            return Err(anyhow!("Failed to get active connections"));
+    /// Loads `DnsProvider` entries from `~/.config/dns_changer/providers.toml`,
+    /// merged with the built-in defaults. Creates the file with the defaults
+    /// on first run so users have something to edit.
+    fn load_providers() -> Result<Vec<DnsProvider>> {
+        let path = Self::config_file_path()?;
+        let mut providers = Self::default_providers();
+
+        if !path.exists() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let config = ProvidersConfig { providers: Self::default_providers() };
+            fs::write(&path, toml::to_string_pretty(&config)?)?;
+            return Ok(providers);
         }
 
-        let output_str = String::from_utf8(output.stdout)?;
-        
-        for line in output_str.lines() {
-            let parts: Vec<&str> = line.split(':').collect();
-            if parts.len() >= 2 && !parts[1].is_empty() {
-                return Ok(parts[0].to_string());
+        let contents = fs::read_to_string(&path)?;
+        let user_config: ProvidersConfig = toml::from_str(&contents)
+            .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))?;
+
+        for user_provider in user_config.providers {
+            if let Some(existing) = providers.iter_mut().find(|p| p.name == user_provider.name) {
+                *existing = user_provider;
+            } else {
+                providers.push(user_provider);
             }
         }
 
-        Err(anyhow!("No active connection found"))
+        Ok(providers)
     }
 
-    fn show_menu(&self) -> Result<()> {
+    async fn show_menu(&self) -> Result<()> {
         println!("========================================");
         println!("        Rust DNS Changer Tool");
         println!("========================================");
@@ -91,6 +187,8 @@ impl DnsChanger {
             "Custom DNS",
             "Automatic DNS (Router)",
             "Show Current DNS",
+            "Benchmark DNS",
+            "Check DNSSEC",
             "Exit",
         ];
 
@@ -101,11 +199,13 @@ impl DnsChanger {
             .interact()?;
 
         match selection {
-            0 => self.select_provider(),
-            1 => self.set_custom_dns(),
-            2 => self.set_automatic_dns(),
-            3 => self.show_current_dns(),
-            4 => {
+            0 => self.select_provider().await,
+            1 => self.set_custom_dns().await,
+            2 => self.backend.set_automatic(&self.current_connection).await,
+            3 => self.backend.show_current(&self.current_connection).await,
+            4 => self.benchmark_dns().await,
+            5 => self.check_dnssec().await,
+            6 => {
                 println!("Goodbye!");
                 std::process::exit(1);
                 // Ok(())
@@ -114,136 +214,246 @@ impl DnsChanger {
         }
     }
 
-    fn select_provider(&self) -> Result<()> {
+    /// Queries a selected provider for both a DNSSEC-signed domain and a
+    /// deliberately bogus-signed one, reporting whether it actually
+    /// validates signatures rather than silently passing bogus answers.
+    async fn check_dnssec(&self) -> Result<()> {
         let provider_names: Vec<String> = self.providers
             .iter()
             .map(|p| format!("{} - {}", p.name, p.description))
             .collect();
 
         let selection = Select::new()
-            .with_prompt("Select DNS Provider")
+            .with_prompt("Select a provider to check")
             .items(&provider_names)
             .default(0)
             .interact()?;
 
         let provider = &self.providers[selection];
-        self.set_dns(&provider.primary_dns, &provider.secondary_dns)?;
-        
-        println!("✅ DNS set to {} ({}, {})", 
-            provider.name, provider.primary_dns, provider.secondary_dns);
-        
-        Ok(())
-    }
+        let server = SocketAddr::new(provider.primary_dns.parse()?, 53);
 
-    fn set_custom_dns(&self) -> Result<()> {
-        let primary: String = Input::new()
-            .with_prompt("Enter primary DNS")
-            .interact_text()?;
+        println!("Checking DNSSEC validation for {}...\n", provider.name);
 
-        let secondary: String = Input::new()
-            .with_prompt("Enter secondary DNS")
-            .interact_text()?;
+        let signed = Self::dnssec_query(server, DNSSEC_SIGNED_DOMAIN).await?;
+        let bogus = Self::dnssec_query(server, DNSSEC_BOGUS_DOMAIN).await?;
 
-        self.set_dns(&primary, &secondary)?;
-        println!("✅ DNS set to custom: {}, {}", primary, secondary);
-        Ok(())
-    }
+        println!("  {} (signed): RRSIG present = {}, AD flag = {}, rcode = {:?}",
+            DNSSEC_SIGNED_DOMAIN, signed.has_rrsig, signed.ad_flag, signed.rcode);
+        println!("  {} (bogus):  rcode = {:?} (expecting SERVFAIL)",
+            DNSSEC_BOGUS_DOMAIN, bogus.rcode);
 
-    fn set_automatic_dns(&self) -> Result<()> {
-        self.execute_command(&[
-            "connection", "mod", &self.current_connection,
-            "ipv4.dns", "",
-            "ipv4.ignore-auto-dns", "no",
-            "ipv6.ignore-auto-dns", "no"
-        ])?;
+        let validates = signed.has_rrsig && signed.ad_flag && bogus.rcode == ResponseCode::ServFail;
+        println!("\n{} DNSSEC validation: {}", provider.name,
+            if validates { "enforced ✅" } else { "NOT enforced ⚠️" });
 
-        self.restart_connection()?;
-        println!("✅ Switched to automatic DNS (Router)");
         Ok(())
     }
 
-    fn set_dns(&self, primary: &str, secondary: &str) -> Result<()> {
-        let dns = format!("{} {}", primary, secondary);
-        
-        self.execute_command(&[
-            "connection", "mod", &self.current_connection,
-            "ipv4.dns", &dns,
-            "ipv4.ignore-auto-dns", "yes"
-        ])?;
-
-        self.restart_connection()?;
-        Ok(())
+    /// Sends a single query with the DNSSEC-OK (DO) bit set and reports
+    /// whether RRSIG records came back, whether the AD flag was set, and
+    /// the response code.
+    async fn dnssec_query(server: SocketAddr, domain: &str) -> Result<DnssecQueryResult> {
+        let stream = UdpClientStream::<tokio::net::UdpSocket>::new(server);
+        let (client, background) = AsyncClient::connect(stream)
+            .await
+            .map_err(|e| anyhow!("Failed to connect to {}: {}", server, e))?;
+        tokio::spawn(background);
+
+        let name = Name::from_ascii(domain)?;
+        let mut query = Query::query(name, RecordType::A);
+        query.set_query_class(DNSClass::IN);
+
+        let mut message = Message::new();
+        message.set_id(rand::random::<u16>());
+        message.set_message_type(MessageType::Query);
+        message.set_op_code(OpCode::Query);
+        message.set_recursion_desired(true);
+        message.add_query(query);
+
+        let mut edns = Edns::new();
+        edns.set_dnssec_ok(true);
+        edns.set_max_payload(4096);
+        message.set_edns(edns);
+
+        let response: DnsResponse = client
+            .send(DnsRequest::new(message, DnsRequestOptions::default()))
+            .next()
+            .await
+            .ok_or_else(|| anyhow!("No response from {}", server))?
+            .map_err(|e| anyhow!("Query to {} failed: {}", server, e))?;
+
+        let has_rrsig = response.answers().iter()
+            .chain(response.additionals())
+            .any(|record| record.record_type() == RecordType::RRSIG);
+
+        Ok(DnssecQueryResult {
+            has_rrsig,
+            ad_flag: response.header().authentic_data(),
+            rcode: response.response_code(),
+        })
     }
 
-    fn execute_command(&self, args: &[&str]) -> Result<()> {
-        let output = Command::new("sudo")
-            .arg("nmcli")
-            .args(args)
-            .output()?;
+    /// Probes every configured provider plus the current router DNS,
+    /// ranks them by a smoothed latency score, and offers to switch to
+    /// whichever came out fastest.
+    async fn benchmark_dns(&self) -> Result<()> {
+        println!("Benchmarking {} DNS servers, {} probes each...\n",
+            self.providers.len() + 1, PROBES_PER_SERVER);
 
-        if !output.status.success() {
-            return Err(anyhow!("Command failed: {}", String::from_utf8_lossy(&output.stderr)));
+        let mut candidates: Vec<(String, String, String)> = self.providers
+            .iter()
+            .map(|p| (p.name.clone(), p.primary_dns.clone(), p.secondary_dns.clone()))
+            .collect();
+        let router_servers = self.backend.current_servers(&self.current_connection).await
+            .unwrap_or_else(|_| vec!["0.0.0.0".to_string()]);
+        candidates.push((
+            "Router (current)".to_string(),
+            router_servers.first().cloned().unwrap_or_else(|| "0.0.0.0".to_string()),
+            router_servers.get(1).cloned().unwrap_or_default(),
+        ));
+
+        let mut results = Vec::with_capacity(candidates.len());
+        for (name, primary, secondary) in candidates {
+            let (score, failures, probes) = Self::probe_server(&primary).await;
+            results.push(BenchmarkResult {
+                name,
+                primary_dns: primary,
+                secondary_dns: secondary,
+                score_ms: score,
+                failures,
+                probes,
+            });
         }
 
-        Ok(())
-    }
+        results.sort_by(|a, b| a.score_ms.partial_cmp(&b.score_ms).unwrap());
+
+        println!("{:<20} {:>10} {:>8}", "Provider", "Score(ms)", "Loss");
+        for r in &results {
+            println!("{:<20} {:>10.1} {:>7}/{}", r.name, r.score_ms, r.failures, r.probes);
+        }
+        println!();
+
+        if let Some(winner) = results.first() {
+            if winner.name == "Router (current)" {
+                println!("🏆 Router DNS is already the fastest option.");
+                return Ok(());
+            }
 
-    fn restart_connection(&self) -> Result<()> {
-        // Bring connection down
-        let _ = Command::new("sudo")
-            .arg("nmcli")
-            .arg("connection")
-            .arg("down")
-            .arg(&self.current_connection)
-            .output();
-
-        // Bring connection up
-        let output = Command::new("sudo")
-            .arg("nmcli")
-            .arg("connection")
-            .arg("up")
-            .arg(&self.current_connection)
-            .output()?;
-
-        if !output.status.success() {
-            return Err(anyhow!("Failed to restart connection: {}", 
-                String::from_utf8_lossy(&output.stderr)));
+            let apply = Confirm::new()
+                .with_prompt(format!("Switch to the fastest provider, {}?", winner.name))
+                .default(true)
+                .interact()?;
+
+            if apply {
+                let dot_hostname = self.providers
+                    .iter()
+                    .find(|p| p.name == winner.name)
+                    .and_then(|p| p.dot_hostname.as_deref());
+                self.backend.set_dns(&self.current_connection, &winner.primary_dns, &winner.secondary_dns, dot_hostname).await?;
+                println!("✅ DNS set to {} ({}, {})",
+                    winner.name, winner.primary_dns, winner.secondary_dns);
+            }
         }
 
         Ok(())
     }
 
-    fn show_current_dns(&self) -> Result<()> {
-        let output = Command::new("nmcli")
-            .arg("connection")
-            .arg("show")
-            .arg(&self.current_connection)
-            .output()?;
-
-        if output.status.success() {
-            let output_str = String::from_utf8(output.stdout)?;
-            for line in output_str.lines() {
-                if line.contains("ipv4.dns") || line.contains("ipv4.ignore-auto-dns") {
-                    println!("{}", line);
+    /// Runs `PROBES_PER_SERVER` queries against `server` across
+    /// `BENCHMARK_DOMAINS` and returns `(ewma_score_ms, failures, probes)`.
+    async fn probe_server(server: &str) -> (f64, usize, usize) {
+        let ip: IpAddr = match server.parse() {
+            Ok(ip) => ip,
+            Err(_) => return (TIMEOUT_PENALTY_MS, PROBES_PER_SERVER, PROBES_PER_SERVER),
+        };
+
+        let mut config = ResolverConfig::new();
+        config.add_name_server(NameServerConfig {
+            socket_addr: SocketAddr::new(ip, 53),
+            protocol: Protocol::Udp,
+            tls_dns_name: None,
+            trust_negative_responses: false,
+            bind_addr: None,
+        });
+
+        let mut opts = ResolverOpts::default();
+        opts.timeout = PROBE_TIMEOUT;
+        opts.attempts = 1;
+        opts.cache_size = 0;
+
+        let resolver = TokioAsyncResolver::tokio(config, opts);
+
+        let mut score = 0.0;
+        let mut failures = 0;
+        let mut probes = 0;
+
+        for round in 0..PROBES_PER_SERVER {
+            let domain = BENCHMARK_DOMAINS[round % BENCHMARK_DOMAINS.len()];
+            let start = Instant::now();
+            let sample_ms = match resolver.lookup_ip(domain).await {
+                Ok(_) => start.elapsed().as_secs_f64() * 1000.0,
+                Err(_) => {
+                    failures += 1;
+                    TIMEOUT_PENALTY_MS
                 }
-            }
+            };
+
+            score = if probes == 0 {
+                sample_ms
+            } else {
+                EWMA_ALPHA * score + (1.0 - EWMA_ALPHA) * sample_ms
+            };
+            probes += 1;
         }
 
-        // Show system DNS info
-        println!("\nSystem DNS configuration:");
-        let _ = Command::new("resolvectl")
-            .arg("status")
-            .status();
+        (score, failures, probes)
+    }
+
+    async fn select_provider(&self) -> Result<()> {
+        let provider_names: Vec<String> = self.providers
+            .iter()
+            .map(|p| format!("{} - {}", p.name, p.description))
+            .collect();
 
+        let selection = Select::new()
+            .with_prompt("Select DNS Provider")
+            .items(&provider_names)
+            .default(0)
+            .interact()?;
+
+        let provider = &self.providers[selection];
+        self.backend.set_dns(
+            &self.current_connection,
+            &provider.primary_dns,
+            &provider.secondary_dns,
+            provider.dot_hostname.as_deref(),
+        ).await?;
+
+        println!("✅ DNS set to {} ({}, {})",
+            provider.name, provider.primary_dns, provider.secondary_dns);
+
+        Ok(())
+    }
+
+    async fn set_custom_dns(&self) -> Result<()> {
+        let primary: String = Input::new()
+            .with_prompt("Enter primary DNS")
+            .interact_text()?;
+
+        let secondary: String = Input::new()
+            .with_prompt("Enter secondary DNS")
+            .interact_text()?;
+
+        self.backend.set_dns(&self.current_connection, &primary, &secondary, None).await?;
+        println!("✅ DNS set to custom: {}, {}", primary, secondary);
         Ok(())
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let dns_changer = DnsChanger::new()?;
+    let dns_changer = DnsChanger::new().await?;
     loop {
-        if let Err(e) = dns_changer.show_menu() {
+        if let Err(e) = dns_changer.show_menu().await {
             eprintln!("Error: {}", e);
         }
         